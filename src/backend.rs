@@ -1,17 +1,26 @@
 use crate::wasm_processor::ProcessorHost;
 use firewheel::{
-    StreamInfo,
     backend::{AudioBackend, DeviceInfo},
     collector::ArcGc,
     processor::FirewheelProcessor,
+    StreamInfo,
 };
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     num::NonZeroU32,
     rc::Rc,
-    sync::{atomic::AtomicBool, mpsc},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering},
+        mpsc,
+    },
+};
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{
+    AudioBuffer, AudioContext, AudioContextOptions, AudioContextState, AudioParam,
+    AudioWorkletNode, ChannelCountMode, GainNode, MediaStream, MediaStreamAudioSourceNode,
+    MediaStreamAudioSourceOptions, MediaStreamConstraints, MediaTrackConstraints, MessageEvent,
+    OfflineAudioContext,
 };
-use web_sys::{AudioContext, AudioContextOptions, AudioWorkletNode};
 
 /// The main-thread host for the Web Audio API backend.
 ///
@@ -27,6 +36,18 @@ pub struct WebAudioBackend {
     alive: ArcGc<AtomicBool>,
     web_context: AudioContext,
     processor_node: Rc<RefCell<Option<AudioWorkletNode>>>,
+    gain_node: GainNode,
+    output_gain: Cell<f32>,
+    muted: Cell<bool>,
+    context_state: ArcGc<AtomicU8>,
+    underrun_count: ArcGc<AtomicU32>,
+    reported_underruns: Cell<u32>,
+    input_capture_failed: ArcGc<AtomicBool>,
+    reported_input_capture_failure: Cell<bool>,
+    // Kept alive only so the `onmessage` listener stays registered; detached in `Drop`.
+    _message_closure: Rc<RefCell<Option<Closure<dyn FnMut(MessageEvent)>>>>,
+    // Kept alive only so the `statechange` listener stays registered; detached in `Drop`.
+    _statechange_closure: Closure<dyn FnMut()>,
 }
 
 impl Drop for WebAudioBackend {
@@ -35,17 +56,41 @@ impl Drop for WebAudioBackend {
             .store(false, std::sync::atomic::Ordering::Relaxed);
 
         if let Some(node) = self.processor_node.borrow().as_ref() {
+            if let Err(e) = node.port().and_then(|port| {
+                port.set_onmessage(None);
+                Ok(())
+            }) {
+                log::error!("Failed to detach the worklet's `MessagePort` listener: {e:?}");
+            }
+
             if let Err(e) = node.disconnect() {
                 log::error!("Failed to disconnect `AudioWorkletNode`: {e:?}");
             }
         }
 
+        if let Err(e) = self.gain_node.disconnect() {
+            log::error!("Failed to disconnect the output `GainNode`: {e:?}");
+        }
+
+        self.web_context.set_onstatechange(None);
+
         if let Err(e) = self.web_context.close() {
             log::error!("Failed to close `AudioContext`: {e:?}");
         }
     }
 }
 
+/// Encodes an `AudioContextState` into the `AtomicU8` mirror shared with
+/// the `statechange` listener.
+fn encode_context_state(state: AudioContextState) -> u8 {
+    match state {
+        AudioContextState::Suspended => 0,
+        AudioContextState::Running => 1,
+        AudioContextState::Closed => 2,
+        _ => 2,
+    }
+}
+
 impl core::fmt::Debug for WebAudioBackend {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("WasmBackend")
@@ -63,6 +108,11 @@ pub enum WebAudioStartError {
     Initialization(String),
     /// An error occurred when constructing the `AudioWorkletNode`.
     WorkletCreation(String),
+    /// Failed to acquire an input stream via `getUserMedia`.
+    InputCapture(String),
+    /// The requested `output_channels` exceeds the destination's
+    /// `maxChannelCount`.
+    UnsupportedChannelCount { requested: u32, max: u32 },
 }
 
 impl core::fmt::Display for WebAudioStartError {
@@ -74,6 +124,18 @@ impl core::fmt::Display for WebAudioStartError {
             Self::WorkletCreation(e) => {
                 write!(f, "Failed to create the backend audio worklet: {e}")
             }
+            Self::InputCapture(e) => {
+                write!(
+                    f,
+                    "Failed to acquire an input stream via `getUserMedia`: {e}"
+                )
+            }
+            Self::UnsupportedChannelCount { requested, max } => {
+                write!(
+                    f,
+                    "Requested {requested} output channels, but the destination only supports up to {max}"
+                )
+            }
         }
     }
 }
@@ -85,6 +147,20 @@ impl std::error::Error for WebAudioStartError {}
 pub enum WebAudioStreamError {
     /// The `AudioWorkletNode` was unexpectedly dropped.
     UnexpectedDrop,
+    /// The `AudioContext` is suspended, most likely because the browser's
+    /// autoplay policy is waiting on a user gesture. Call
+    /// [`WebAudioBackend::resume`] in response to one to start the graph.
+    Suspended,
+    /// The worklet reported one or more xruns/underruns over its
+    /// `MessagePort` since the last time this was reported.
+    Underrun {
+        /// The total number of underruns reported by the worklet so far.
+        count: u32,
+    },
+    /// `request_input` was set, but acquiring the input stream via
+    /// `getUserMedia` failed (e.g. the user denied the permission prompt).
+    /// The stream otherwise continues running with no input.
+    InputCaptureFailed,
 }
 
 impl core::fmt::Display for WebAudioStreamError {
@@ -93,6 +169,21 @@ impl core::fmt::Display for WebAudioStreamError {
             Self::UnexpectedDrop => {
                 write!(f, "The `AudioWorkletNode` was unexpectedly dropped")
             }
+            Self::Suspended => {
+                write!(
+                    f,
+                    "The `AudioContext` is suspended; call `WebAudioBackend::resume` from a user gesture"
+                )
+            }
+            Self::Underrun { count } => {
+                write!(f, "The audio worklet has reported {count} underrun(s)")
+            }
+            Self::InputCaptureFailed => {
+                write!(
+                    f,
+                    "Failed to acquire an input stream via `getUserMedia`; the stream is running without input"
+                )
+            }
         }
     }
 }
@@ -104,6 +195,28 @@ impl std::error::Error for WebAudioStreamError {}
 pub struct WebAudioConfig {
     /// The desired sample rate.
     pub sample_rate: Option<NonZeroU32>,
+    /// Whether to request microphone/line-in capture via `getUserMedia`.
+    ///
+    /// Browsers gate this behind a user gesture and an explicit permission
+    /// grant, so the resulting stream is only wired up once the promise
+    /// resolves; until then the worklet simply receives no input.
+    pub request_input: bool,
+    /// The number of input channels to request when `request_input` is set.
+    ///
+    /// Defaults to a single (mono) channel. This is requested from
+    /// `getUserMedia` as an exact `channelCount` constraint, and the
+    /// worklet's input is additionally forced to this many channels via an
+    /// explicit `channelCount`/`channelCountMode`, so `inputs[0]` is
+    /// guaranteed to match the `input_buffers` sizing regardless of what
+    /// the underlying track actually provides.
+    pub input_channels: Option<NonZeroU32>,
+    /// The number of output channels to render.
+    ///
+    /// Defaults to stereo (2 channels). The requested count is validated
+    /// against the destination's `maxChannelCount`; exceeding it fails
+    /// [`WebAudioBackend::start_stream`] with
+    /// [`WebAudioStartError::UnsupportedChannelCount`].
+    pub output_channels: Option<NonZeroU32>,
 }
 
 impl AudioBackend for WebAudioBackend {
@@ -116,9 +229,36 @@ impl AudioBackend for WebAudioBackend {
     }
 
     fn available_output_devices() -> Vec<DeviceInfo> {
+        // Probing the destination's channel capacity requires spinning up a
+        // throwaway `AudioContext`, and closing one is asynchronous — so a
+        // host that polls this for hotplug detection could otherwise rack
+        // up more concurrently-live contexts than the browser allows. Probe
+        // once per page and cache the result instead.
+        thread_local! {
+            static MAX_OUTPUT_CHANNELS: Cell<Option<u32>> = const { Cell::new(None) };
+        }
+
+        let num_channels = MAX_OUTPUT_CHANNELS.with(|cached| {
+            if let Some(num_channels) = cached.get() {
+                return num_channels;
+            }
+
+            let num_channels = web_sys::AudioContext::new()
+                .map(|context| {
+                    let max_channels = context.destination().max_channel_count();
+                    if let Err(e) = context.close() {
+                        log::error!("Failed to close the probe `AudioContext`: {e:?}");
+                    }
+                    max_channels
+                })
+                .unwrap_or(2);
+            cached.set(Some(num_channels));
+            num_channels
+        });
+
         vec![DeviceInfo {
             name: "default output".into(),
-            num_channels: 2,
+            num_channels,
             is_default: true,
         }]
     }
@@ -138,8 +278,29 @@ impl AudioBackend for WebAudioBackend {
         };
 
         let sample_rate = context.sample_rate();
-        let inputs = 0;
-        let outputs = 2;
+        let inputs = if config.request_input {
+            config.input_channels.map(NonZeroU32::get).unwrap_or(1) as usize
+        } else {
+            0
+        };
+        let outputs = config.output_channels.map(NonZeroU32::get).unwrap_or(2);
+        let max_channels = context.destination().max_channel_count();
+        if outputs > max_channels {
+            if let Err(e) = context.close() {
+                log::error!("Failed to close `AudioContext`: {e:?}");
+            }
+            return Err(WebAudioStartError::UnsupportedChannelCount {
+                requested: outputs,
+                max: max_channels,
+            });
+        }
+        let outputs = outputs as usize;
+
+        let gain_node = GainNode::new(&context)
+            .map_err(|e| WebAudioStartError::Initialization(format!("{e:?}")))?;
+        gain_node
+            .connect_with_audio_node(&context.destination())
+            .map_err(|e| WebAudioStartError::Initialization(format!("{e:?}")))?;
 
         fn create_buffer(len: usize) -> &'static mut [f32] {
             let mut vec = Vec::new();
@@ -161,9 +322,17 @@ impl AudioBackend for WebAudioBackend {
         let wrapper = wrapper.pack();
 
         let processor_node = Rc::new(RefCell::new(None));
+        let message_closure = Rc::new(RefCell::new(None));
+        let underrun_count = ArcGc::new(AtomicU32::new(0));
+        let input_capture_failed = ArcGc::new(AtomicBool::new(false));
+        let request_input = config.request_input;
         let prepare_worklet = {
             let context = context.clone();
             let processor_node = processor_node.clone();
+            let message_closure = message_closure.clone();
+            let underrun_count = underrun_count.clone();
+            let input_capture_failed = input_capture_failed.clone();
+            let gain_node = gain_node.clone();
             async move {
                 let mod_url = crate::dynamic_module::dependent_module!("./js/audio-worklet.js")?;
                 wasm_bindgen_futures::JsFuture::from(
@@ -177,6 +346,16 @@ impl AudioBackend for WebAudioBackend {
                     web_sys::AudioWorkletNode::new_with_options(&context, "WasmProcessor", &{
                         let options = web_sys::AudioWorkletNodeOptions::new();
 
+                        options.set_number_of_inputs(if inputs > 0 { 1 } else { 0 });
+                        if inputs > 0 {
+                            // Force the connected source to up/down-mix to exactly
+                            // `inputs` channels, regardless of what the negotiated
+                            // `MediaStreamTrack` actually provides, so `inputs[0]`
+                            // always matches the `input_buffers` sizing.
+                            options.set_channel_count(inputs as u32);
+                            options.set_channel_count_mode(ChannelCountMode::Explicit);
+                        }
+
                         let output_channels = js_sys::Array::new_with_length(1);
                         output_channels.set(0, outputs.into());
                         options.set_output_channel_count(&output_channels);
@@ -189,7 +368,31 @@ impl AudioBackend for WebAudioBackend {
                         options
                     })?;
 
-                node.connect_with_audio_node(&context.destination())?;
+                if request_input {
+                    match capture_input_stream(&context, inputs as u32).await {
+                        Ok(source) => {
+                            source.connect_with_audio_node(&node)?;
+                        }
+                        Err(e) => {
+                            log::error!("{}", WebAudioStartError::InputCapture(format!("{e:?}")));
+                            input_capture_failed.store(true, Ordering::Relaxed);
+                        }
+                    }
+                }
+
+                let onmessage = {
+                    let underrun_count = underrun_count.clone();
+                    Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                        if let Some(count) = event.data().as_f64() {
+                            underrun_count.store(count as u32, Ordering::Relaxed);
+                        }
+                    })
+                };
+                node.port()?
+                    .set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+                *message_closure.borrow_mut() = Some(onmessage);
+
+                node.connect_with_audio_node(&gain_node)?;
                 *processor_node.borrow_mut() = Some(node);
 
                 Ok::<_, wasm_bindgen::JsValue>(())
@@ -202,13 +405,33 @@ impl AudioBackend for WebAudioBackend {
             }
         });
 
+        let context_state = ArcGc::new(AtomicU8::new(encode_context_state(context.state())));
+        let statechange_closure = {
+            let context = context.clone();
+            let context_state = context_state.clone();
+            Closure::<dyn FnMut()>::new(move || {
+                context_state.store(encode_context_state(context.state()), Ordering::Relaxed);
+            })
+        };
+        context.set_onstatechange(Some(statechange_closure.as_ref().unchecked_ref()));
+
         Ok((
             Self {
                 web_context: context,
                 is_dropped: false,
                 processor: sender,
                 processor_node,
+                gain_node,
+                output_gain: Cell::new(1.0),
+                muted: Cell::new(false),
                 alive,
+                context_state,
+                underrun_count,
+                reported_underruns: Cell::new(0),
+                input_capture_failed,
+                reported_input_capture_failure: Cell::new(false),
+                _message_closure: message_closure,
+                _statechange_closure: statechange_closure,
             },
             StreamInfo {
                 sample_rate: NonZeroU32::new(sample_rate as u32)
@@ -232,8 +455,466 @@ impl AudioBackend for WebAudioBackend {
     fn poll_status(&mut self) -> Result<(), Self::StreamError> {
         if self.is_dropped {
             Err(WebAudioStreamError::UnexpectedDrop)
+        } else if self.context_state.load(Ordering::Relaxed)
+            == encode_context_state(AudioContextState::Suspended)
+        {
+            Err(WebAudioStreamError::Suspended)
+        } else if self.input_capture_failed.load(Ordering::Relaxed)
+            && !self.reported_input_capture_failure.get()
+        {
+            self.reported_input_capture_failure.set(true);
+            Err(WebAudioStreamError::InputCaptureFailed)
+        } else {
+            let count = self.underrun_count.load(Ordering::Relaxed);
+            if count != self.reported_underruns.get() {
+                self.reported_underruns.set(count);
+                Err(WebAudioStreamError::Underrun { count })
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+impl WebAudioBackend {
+    /// Resumes the underlying `AudioContext`, e.g. in response to a user
+    /// gesture needed to satisfy the browser's autoplay policy.
+    pub async fn resume(&self) -> Result<(), wasm_bindgen::JsValue> {
+        wasm_bindgen_futures::JsFuture::from(self.web_context.resume()?).await?;
+        Ok(())
+    }
+
+    /// Sets the overall output gain, ramped in linearly over a few
+    /// milliseconds to avoid an audible click.
+    ///
+    /// Has no audible effect while [`Self::set_muted`] is in effect, but
+    /// is remembered and restored on unmute.
+    pub fn set_output_gain(&self, gain: f32) {
+        self.output_gain.set(gain);
+        if !self.muted.get() {
+            self.ramp_gain_to(gain);
+        }
+    }
+
+    /// Mutes or unmutes the entire Firewheel output at the browser level,
+    /// without reconfiguring the graph or touching the worklet's shared
+    /// memory.
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.set(muted);
+        self.ramp_gain_to(if muted { 0.0 } else { self.output_gain.get() });
+    }
+
+    fn ramp_gain_to(&self, gain: f32) {
+        const RAMP_SECONDS: f64 = 0.01;
+
+        let param = self.gain_node.gain();
+        let now = self.web_context.current_time();
+        // `cancel_and_hold_at_time` (rather than cancel + read + re-set) is
+        // required here: `cancelScheduledValues` doesn't reliably hand back
+        // the true interpolated value mid-ramp, so re-deriving the start
+        // point from `param.value()` can produce an audible jump if this
+        // is called again while a previous ramp is still in flight.
+        if let Err(e) = param
+            .cancel_and_hold_at_time(now)
+            .and_then(|p| p.linear_ramp_to_value_at_time(gain, now + RAMP_SECONDS))
+        {
+            log::error!("Failed to ramp output gain: {e:?}");
+        }
+    }
+
+    /// Sends a lightweight runtime command to the running
+    /// `AudioWorkletProcessor` over its `MessagePort`.
+    ///
+    /// Has no effect if the worklet hasn't finished initializing yet.
+    pub fn send_command(&self, command: WorkletCommand) -> Result<(), wasm_bindgen::JsValue> {
+        let node = self.processor_node.borrow();
+        let Some(node) = node.as_ref() else {
+            return Ok(());
+        };
+
+        let bytes = command.to_bytes();
+        let array = js_sys::Uint8Array::new_with_length(bytes.len() as u32);
+        array.copy_from(&bytes);
+        node.port()?.post_message(&array)?;
+
+        Ok(())
+    }
+}
+
+/// Lightweight runtime commands sent to the running `AudioWorkletProcessor`
+/// over its `MessagePort`.
+///
+/// These are one-way (host-to-worklet) only: the `onmessage` handler on the
+/// host side currently only understands the worklet's underrun counter, so
+/// there is no tagged response format a query-style command could report
+/// back through yet. A latency query belongs here once that wire format
+/// exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkletCommand {
+    /// Flushes any audio currently buffered in the worklet.
+    Flush,
+    /// Resets the worklet's internal sample clock to zero.
+    ResetClock,
+}
+
+impl WorkletCommand {
+    fn to_bytes(self) -> [u8; 1] {
+        match self {
+            Self::Flush => [0],
+            Self::ResetClock => [1],
+        }
+    }
+}
+
+/// Requests microphone/line-in access via `getUserMedia` and wraps the
+/// resulting stream in a `MediaStreamAudioSourceNode` on `context`.
+///
+/// `channels` is requested as an exact `channelCount` constraint so the
+/// negotiated track matches what the caller sized its buffers for.
+async fn capture_input_stream(
+    context: &AudioContext,
+    channels: u32,
+) -> Result<MediaStreamAudioSourceNode, wasm_bindgen::JsValue> {
+    let media_devices = web_sys::window()
+        .ok_or_else(|| wasm_bindgen::JsValue::from_str("no `window` available"))?
+        .navigator()
+        .media_devices()?;
+
+    let channel_count = web_sys::ConstrainULongRange::new();
+    channel_count.set_exact(channels);
+
+    let track_constraints = MediaTrackConstraints::new();
+    track_constraints.set_channel_count(&channel_count);
+
+    let constraints = MediaStreamConstraints::new();
+    constraints.set_audio(&track_constraints);
+
+    let stream: MediaStream = wasm_bindgen_futures::JsFuture::from(
+        media_devices.get_user_media_with_constraints(&constraints)?,
+    )
+    .await?
+    .unchecked_into();
+
+    MediaStreamAudioSourceNode::new(context, &MediaStreamAudioSourceOptions::new(&stream))
+}
+
+/// Faster-than-realtime rendering backend built on `OfflineAudioContext`.
+///
+/// Unlike [`WebAudioBackend`], this doesn't drive a live output device:
+/// the graph is rendered for a fixed number of frames and resolved into
+/// plain sample buffers via [`OfflineWebAudioBackend::render`], which is
+/// useful for bouncing stems or running deterministic tests in headless
+/// Wasm.
+pub struct OfflineWebAudioBackend {
+    processor: mpsc::Sender<FirewheelProcessor>,
+    is_dropped: bool,
+    alive: ArcGc<AtomicBool>,
+    web_context: OfflineAudioContext,
+    processor_node: Rc<RefCell<Option<AudioWorkletNode>>>,
+    rendered: bool,
+    // Resolved once the worklet has been attached to the offline context's
+    // destination, so `render` never races `prepare_worklet`.
+    worklet_ready: Rc<RefCell<WorkletReadyState>>,
+}
+
+/// Tracks whether the offline worklet has finished attaching, so `render`
+/// can await it instead of racing the `spawn_local`'d setup future.
+enum WorkletReadyState {
+    Pending(Option<std::task::Waker>),
+    Ready(Result<(), wasm_bindgen::JsValue>),
+}
+
+fn signal_worklet_ready(
+    state: &Rc<RefCell<WorkletReadyState>>,
+    result: Result<(), wasm_bindgen::JsValue>,
+) {
+    let waker = match std::mem::replace(&mut *state.borrow_mut(), WorkletReadyState::Ready(result))
+    {
+        WorkletReadyState::Pending(waker) => waker,
+        WorkletReadyState::Ready(_) => None,
+    };
+    if let Some(waker) = waker {
+        waker.wake();
+    }
+}
+
+async fn wait_for_worklet_ready(
+    state: Rc<RefCell<WorkletReadyState>>,
+) -> Result<(), wasm_bindgen::JsValue> {
+    std::future::poll_fn(move |cx| {
+        let mut state = state.borrow_mut();
+        match &mut *state {
+            WorkletReadyState::Pending(waker) => {
+                *waker = Some(cx.waker().clone());
+                std::task::Poll::Pending
+            }
+            WorkletReadyState::Ready(result) => std::task::Poll::Ready(result.clone()),
+        }
+    })
+    .await
+}
+
+impl Drop for OfflineWebAudioBackend {
+    fn drop(&mut self) {
+        self.alive
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+
+        if let Some(node) = self.processor_node.borrow().as_ref() {
+            if let Err(e) = node.disconnect() {
+                log::error!("Failed to disconnect `AudioWorkletNode`: {e:?}");
+            }
+        }
+    }
+}
+
+impl core::fmt::Debug for OfflineWebAudioBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OfflineWebAudioBackend")
+            .field("is_dropped", &self.is_dropped)
+            .field("alive", &self.alive)
+            .field("rendered", &self.rendered)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Errors related to initializing the offline rendering stream.
+#[derive(Debug)]
+pub enum OfflineStartError {
+    /// An error occurred during `OfflineAudioContext` initialization.
+    Initialization(String),
+    /// An error occurred when constructing the `AudioWorkletNode`.
+    WorkletCreation(String),
+}
+
+impl core::fmt::Display for OfflineStartError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Initialization(e) => {
+                write!(f, "Failed to initialize `OfflineAudioContext`: {e}")
+            }
+            Self::WorkletCreation(e) => {
+                write!(f, "Failed to create the backend audio worklet: {e}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OfflineStartError {}
+
+/// Errors encountered while the offline rendering stream is running.
+#[derive(Debug)]
+pub enum OfflineStreamError {
+    /// The `AudioWorkletNode` was unexpectedly dropped.
+    UnexpectedDrop,
+    /// Rendering has finished: [`OfflineWebAudioBackend::render`] has
+    /// already returned the rendered audio.
+    Complete,
+}
+
+impl core::fmt::Display for OfflineStreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedDrop => {
+                write!(f, "The `AudioWorkletNode` was unexpectedly dropped")
+            }
+            Self::Complete => {
+                write!(f, "Offline rendering has finished")
+            }
+        }
+    }
+}
+
+impl std::error::Error for OfflineStreamError {}
+
+/// The offline rendering backend's configuration.
+#[derive(Debug, Clone)]
+pub struct OfflineWebAudioConfig {
+    /// The number of frames to render.
+    pub length_frames: u32,
+    /// The sample rate to render at.
+    pub sample_rate: NonZeroU32,
+    /// The number of output channels to render.
+    pub output_channels: NonZeroU32,
+}
+
+/// The per-channel output of an [`OfflineWebAudioBackend::render`] call.
+#[derive(Debug, Clone)]
+pub struct RenderedAudio {
+    /// One `Vec<f32>` of samples per rendered channel.
+    pub channels: Vec<Vec<f32>>,
+    /// The sample rate the audio was rendered at.
+    pub sample_rate: u32,
+}
+
+impl AudioBackend for OfflineWebAudioBackend {
+    type Config = OfflineWebAudioConfig;
+    type StartStreamError = OfflineStartError;
+    type StreamError = OfflineStreamError;
+
+    fn available_input_devices() -> Vec<DeviceInfo> {
+        vec![]
+    }
+
+    fn available_output_devices() -> Vec<DeviceInfo> {
+        vec![]
+    }
+
+    fn start_stream(config: Self::Config) -> Result<(Self, StreamInfo), Self::StartStreamError> {
+        let (sender, receiver) = mpsc::channel();
+
+        let outputs = config.output_channels.get() as usize;
+        let inputs = 0;
+
+        let context = OfflineAudioContext::new_with_number_of_channels_and_length_and_sample_rate(
+            outputs as u32,
+            config.length_frames,
+            config.sample_rate.get() as f32,
+        )
+        .map_err(|e| OfflineStartError::Initialization(format!("{e:?}")))?;
+
+        let sample_rate = context.sample_rate();
+
+        fn create_buffer(len: usize) -> &'static mut [f32] {
+            let mut vec = Vec::new();
+            vec.reserve_exact(len);
+            vec.extend(std::iter::repeat_n(0f32, len));
+            Vec::leak(vec)
+        }
+
+        let alive = ArcGc::new(AtomicBool::new(true));
+        let wrapper = ProcessorHost {
+            processor: None,
+            receiver,
+            alive: alive.clone(),
+            inputs,
+            input_buffers: create_buffer(inputs * crate::BLOCK_FRAMES),
+            outputs,
+            output_buffers: create_buffer(outputs * crate::BLOCK_FRAMES),
+        };
+        let wrapper = wrapper.pack();
+
+        let processor_node = Rc::new(RefCell::new(None));
+        let prepare_worklet = {
+            let context = context.clone();
+            let processor_node = processor_node.clone();
+            async move {
+                let mod_url = crate::dynamic_module::dependent_module!("./js/audio-worklet.js")?;
+                wasm_bindgen_futures::JsFuture::from(
+                    context
+                        .audio_worklet()?
+                        .add_module(mod_url.trim_start_matches('.'))?,
+                )
+                .await?;
+
+                let node =
+                    web_sys::AudioWorkletNode::new_with_options(&context, "WasmProcessor", &{
+                        let options = web_sys::AudioWorkletNodeOptions::new();
+
+                        options.set_number_of_inputs(0);
+
+                        let output_channels = js_sys::Array::new_with_length(1);
+                        output_channels.set(0, outputs.into());
+                        options.set_output_channel_count(&output_channels);
+
+                        options.set_processor_options(Some(&js_sys::Array::of3(
+                            &wasm_bindgen::module(),
+                            &wasm_bindgen::memory(),
+                            &wrapper.into(),
+                        )));
+                        options
+                    })?;
+
+                node.connect_with_audio_node(&context.destination())?;
+                *processor_node.borrow_mut() = Some(node);
+
+                Ok::<_, wasm_bindgen::JsValue>(())
+            }
+        };
+
+        let worklet_ready = Rc::new(RefCell::new(WorkletReadyState::Pending(None)));
+        {
+            let worklet_ready = worklet_ready.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = prepare_worklet.await;
+                if let Err(e) = &result {
+                    log::error!("failed to initialize offline audio worklet: {e:?}");
+                }
+                signal_worklet_ready(&worklet_ready, result);
+            });
+        }
+
+        Ok((
+            Self {
+                web_context: context,
+                is_dropped: false,
+                processor: sender,
+                processor_node,
+                alive,
+                rendered: false,
+                worklet_ready,
+            },
+            StreamInfo {
+                sample_rate: NonZeroU32::new(sample_rate as u32)
+                    .expect("Web Audio API sample rate should be non-zero"),
+                max_block_frames: NonZeroU32::new(crate::BLOCK_FRAMES as u32).unwrap(),
+                num_stream_in_channels: inputs as u32,
+                num_stream_out_channels: outputs as u32,
+                input_device_name: None,
+                output_device_name: None,
+                ..Default::default()
+            },
+        ))
+    }
+
+    fn set_processor(&mut self, processor: FirewheelProcessor) {
+        if self.processor.send(processor).is_err() {
+            self.is_dropped = true;
+        }
+    }
+
+    fn poll_status(&mut self) -> Result<(), Self::StreamError> {
+        if self.is_dropped {
+            Err(OfflineStreamError::UnexpectedDrop)
+        } else if self.rendered {
+            Err(OfflineStreamError::Complete)
         } else {
             Ok(())
         }
     }
 }
+
+impl OfflineWebAudioBackend {
+    /// Renders the configured graph for its full length and resolves the
+    /// result into per-channel sample buffers.
+    ///
+    /// This drives `OfflineAudioContext::startRendering` to completion in
+    /// one shot rather than polling in realtime; call it once the
+    /// processor has been installed via [`AudioBackend::set_processor`].
+    ///
+    /// Internally awaits the worklet's attachment to the offline context
+    /// before starting rendering, so the result is deterministic even if
+    /// called immediately after [`AudioBackend::start_stream`].
+    pub async fn render(&mut self) -> Result<RenderedAudio, wasm_bindgen::JsValue> {
+        wait_for_worklet_ready(self.worklet_ready.clone()).await?;
+
+        let buffer: AudioBuffer =
+            wasm_bindgen_futures::JsFuture::from(self.web_context.start_rendering()?)
+                .await?
+                .unchecked_into();
+
+        let channels = (0..buffer.number_of_channels())
+            .map(|channel| {
+                let mut data = vec![0f32; buffer.length() as usize];
+                buffer.copy_from_channel(&mut data, channel as i32)?;
+                Ok(data)
+            })
+            .collect::<Result<Vec<_>, wasm_bindgen::JsValue>>()?;
+
+        self.rendered = true;
+
+        Ok(RenderedAudio {
+            channels,
+            sample_rate: buffer.sample_rate() as u32,
+        })
+    }
+}